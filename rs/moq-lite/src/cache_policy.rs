@@ -1,9 +1,36 @@
+mod clock;
+mod dedup;
+mod directive;
 mod pattern_based;
 
+pub use clock::{Clock, MockClock, RealClock};
+pub use dedup::{ChunkKey, ChunkStore, DedupFrame, DedupStore, FastCdc};
+pub use directive::{CacheDirectives, DirectiveCachePolicy};
 pub use pattern_based::PatternBasedCachePolicy;
 
+use std::time::Instant;
+
 use crate::Path;
 
+/// Identifies a cached group slated for eviction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvictId {
+	pub broadcast_path: Path,
+	pub track_name: String,
+	pub group_sequence: u64,
+}
+
+/// A snapshot of one cached group, used by [`CachePolicy::on_over_budget`] to decide what to evict
+#[derive(Debug, Clone)]
+pub struct CacheEntryStat {
+	pub broadcast_path: Path,
+	pub track_name: String,
+	pub group_sequence: u64,
+	pub byte_size: u64,
+	pub last_access: Instant,
+	pub track_priority: u8,
+}
+
 /// Decision on whether to cache an item
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CacheDecision {
@@ -35,6 +62,42 @@ pub trait CachePolicy: Send + Sync {
 
 	/// Check if backup broadcasts should be kept
 	fn should_keep_backup(&self, age_seconds: u64, backup_count: usize) -> bool;
+
+	/// Whether cached frames should be content-defined-chunked and deduplicated against
+	/// other cached frames (see [`crate::DedupStore`])
+	fn dedup_enabled(&self) -> bool {
+		false
+	}
+
+	/// The cache is `current_bytes` bytes large against a `limit` byte budget; choose which
+	/// of `candidates` to evict to bring it back under budget. Called for both the cache-wide
+	/// budget and, separately, for each broadcast's own budget.
+	fn on_over_budget(&self, current_bytes: u64, limit: u64, candidates: &[CacheEntryStat]) -> Vec<EvictId> {
+		let _ = (current_bytes, limit, candidates);
+		Vec::new()
+	}
+
+	/// Whether another frame can be admitted into a group that already holds
+	/// `current_frames_in_group` frames
+	fn should_admit_frame(&self, track: &Path, group_seq: u64, current_frames_in_group: usize) -> CacheDecision {
+		let _ = (track, group_seq, current_frames_in_group);
+		CacheDecision::Cache
+	}
+
+	/// Whether another group can be admitted into a track that already holds `current_groups`
+	/// cached groups. Policies with a ring-buffer limit (e.g. "keep latest N") always admit the
+	/// new group here and instead signal the caller to evict the oldest one.
+	fn should_admit_group(&self, track: &Path, current_groups: usize) -> CacheDecision {
+		let _ = (track, current_groups);
+		CacheDecision::Cache
+	}
+
+	/// Whether another track can be admitted into a broadcast that already holds
+	/// `current_tracks` cached tracks
+	fn should_admit_track(&self, broadcast: &Path, current_tracks: usize) -> CacheDecision {
+		let _ = (broadcast, current_tracks);
+		CacheDecision::Cache
+	}
 }
 
 /// Always cache everything (default behavior, backward compatible)
@@ -130,4 +193,29 @@ mod tests {
 		assert_eq!(policy.should_cache_frame(512), CacheDecision::NoCache);
 		assert!(!policy.should_keep_backup(3600, 10));
 	}
+
+	#[test]
+	fn test_default_on_over_budget_evicts_nothing() {
+		let policy = AlwaysCachePolicy;
+		let candidates = vec![CacheEntryStat {
+			broadcast_path: Path::new("test"),
+			track_name: "video".to_string(),
+			group_sequence: 1,
+			byte_size: 1024,
+			last_access: Instant::now(),
+			track_priority: 0,
+		}];
+
+		assert!(policy.on_over_budget(2048, 1024, &candidates).is_empty());
+	}
+
+	#[test]
+	fn test_default_admission_always_admits() {
+		let policy = AlwaysCachePolicy;
+		let path = Path::new("test");
+
+		assert_eq!(policy.should_admit_frame(&path, 1, 1000), CacheDecision::Cache);
+		assert_eq!(policy.should_admit_group(&path, 1000), CacheDecision::Cache);
+		assert_eq!(policy.should_admit_track(&path, 1000), CacheDecision::Cache);
+	}
 }
@@ -0,0 +1,75 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Abstracts wall-clock access so time-based cache behavior (backup TTL, sweep interval) can
+/// be driven deterministically in tests instead of depending on real elapsed time.
+pub trait Clock: Send + Sync + std::fmt::Debug {
+	fn now(&self) -> Instant;
+}
+
+/// Clock backed by the real system monotonic clock
+#[derive(Debug, Clone, Default)]
+pub struct RealClock;
+
+impl Clock for RealClock {
+	fn now(&self) -> Instant {
+		Instant::now()
+	}
+}
+
+/// A clock that only advances when told to, for deterministic tests of TTL expiry and sweep
+/// timing
+#[derive(Debug, Clone)]
+pub struct MockClock {
+	now: Arc<Mutex<Instant>>,
+}
+
+impl MockClock {
+	/// Start the mock clock at the current real time
+	pub fn new() -> Self {
+		Self {
+			now: Arc::new(Mutex::new(Instant::now())),
+		}
+	}
+
+	/// Move the mock clock forward by `duration`
+	pub fn advance(&self, duration: Duration) {
+		let mut now = self.now.lock().expect("mock clock lock poisoned");
+		*now += duration;
+	}
+}
+
+impl Default for MockClock {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Clock for MockClock {
+	fn now(&self) -> Instant {
+		*self.now.lock().expect("mock clock lock poisoned")
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_mock_clock_advances_on_demand() {
+		let clock = MockClock::new();
+		let start = clock.now();
+
+		clock.advance(Duration::from_secs(30));
+		assert_eq!(clock.now(), start + Duration::from_secs(30));
+	}
+
+	#[test]
+	fn test_mock_clock_shared_across_clones() {
+		let clock = MockClock::new();
+		let cloned = clock.clone();
+
+		clock.advance(Duration::from_secs(5));
+		assert_eq!(clock.now(), cloned.now());
+	}
+}
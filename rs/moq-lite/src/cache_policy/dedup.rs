@@ -0,0 +1,313 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Number of distinct entries in the rolling-fingerprint gear table
+const GEAR_SIZE: usize = 256;
+
+/// Content-defined chunk boundary, using FastCDC's normalized chunking
+#[derive(Debug, Clone)]
+pub struct FastCdc {
+	gear: Arc<[u64; GEAR_SIZE]>,
+	min_size: usize,
+	avg_size: usize,
+	max_size: usize,
+	mask_small: u64,
+	mask_large: u64,
+}
+
+impl FastCdc {
+	/// Build a chunker targeting `avg_size` bytes per chunk, bounded by `[min_size, max_size]`.
+	pub fn new(min_size: usize, avg_size: usize, max_size: usize) -> Self {
+		// The mask bit count controls how likely a cut is at any given byte: more set bits
+		// means a rarer match (used while still under `avg_size`), fewer bits means a more
+		// frequent match (used once past `avg_size`, pulling the cut back toward the target).
+		let bits = (avg_size.max(1).ilog2()).max(4);
+		let mask_small = (1u64 << (bits + 1)) - 1;
+		let mask_large = (1u64 << bits.saturating_sub(1)) - 1;
+
+		Self {
+			gear: Arc::new(gear_table()),
+			min_size,
+			avg_size,
+			max_size,
+			mask_small,
+			mask_large,
+		}
+	}
+
+	/// Split `data` into content-defined chunks, returning each chunk's exclusive end offset.
+	pub fn cut_points(&self, data: &[u8]) -> Vec<usize> {
+		let mut cuts = Vec::new();
+		let mut offset = 0;
+
+		while offset < data.len() {
+			let remaining = &data[offset..];
+			let cut_len = self.next_cut(remaining);
+			offset += cut_len;
+			cuts.push(offset);
+		}
+
+		cuts
+	}
+
+	/// Find the next cut point within `data`, relative to its start.
+	fn next_cut(&self, data: &[u8]) -> usize {
+		let min = self.min_size.min(data.len());
+		let avg = self.avg_size.min(data.len());
+		let max = self.max_size.min(data.len());
+
+		if data.len() <= min {
+			return data.len();
+		}
+
+		let mut fp: u64 = 0;
+		let mut i = min;
+
+		while i < avg {
+			fp = (fp << 1).wrapping_add(self.gear[data[i] as usize]);
+			if fp & self.mask_small == 0 {
+				return i + 1;
+			}
+			i += 1;
+		}
+
+		while i < max {
+			fp = (fp << 1).wrapping_add(self.gear[data[i] as usize]);
+			if fp & self.mask_large == 0 {
+				return i + 1;
+			}
+			i += 1;
+		}
+
+		max
+	}
+}
+
+/// A deterministic gear table, shared across all chunkers since it only needs to be random
+/// once (not per-instance): generated with a fixed-seed splitmix64 so builds stay reproducible.
+fn gear_table() -> [u64; GEAR_SIZE] {
+	static TABLE: OnceLock<[u64; GEAR_SIZE]> = OnceLock::new();
+	*TABLE.get_or_init(|| {
+		let mut table = [0u64; GEAR_SIZE];
+		let mut seed = 0x9E3779B97F4A7C15u64;
+		for slot in table.iter_mut() {
+			seed = seed.wrapping_add(0x9E3779B97F4A7C15);
+			let mut z = seed;
+			z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+			z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+			*slot = z ^ (z >> 31);
+		}
+		table
+	})
+}
+
+/// Content-addressed key for a stored chunk (a blake3 hash)
+pub type ChunkKey = [u8; 32];
+
+struct ChunkEntry {
+	data: Arc<[u8]>,
+	refcount: usize,
+}
+
+/// A refcounted, content-addressed store of chunk payloads, shared across cached frames
+#[derive(Default)]
+pub struct ChunkStore {
+	chunks: Mutex<HashMap<ChunkKey, ChunkEntry>>,
+}
+
+impl ChunkStore {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Returns whether a chunk with this content is already stored
+	pub fn contains(&self, key: &ChunkKey) -> bool {
+		self.chunks.lock().expect("chunk store lock poisoned").contains_key(key)
+	}
+
+	/// Store a chunk, bumping its refcount if the content is already present
+	pub fn insert(&self, data: &[u8]) -> ChunkKey {
+		let key = *blake3::hash(data).as_bytes();
+		let mut chunks = self.chunks.lock().expect("chunk store lock poisoned");
+		chunks
+			.entry(key)
+			.and_modify(|entry| entry.refcount += 1)
+			.or_insert_with(|| ChunkEntry {
+				data: Arc::from(data),
+				refcount: 1,
+			});
+		key
+	}
+
+	/// Fetch a chunk's payload by key
+	pub fn get(&self, key: &ChunkKey) -> Option<Arc<[u8]>> {
+		self.chunks
+			.lock()
+			.expect("chunk store lock poisoned")
+			.get(key)
+			.map(|entry| entry.data.clone())
+	}
+
+	/// Drop a reference to a chunk, reclaiming it once nothing references it anymore
+	pub fn release(&self, key: &ChunkKey) {
+		let mut chunks = self.chunks.lock().expect("chunk store lock poisoned");
+		if let Some(entry) = chunks.get_mut(key) {
+			entry.refcount -= 1;
+			if entry.refcount == 0 {
+				chunks.remove(key);
+			}
+		}
+	}
+
+	/// Number of distinct chunks currently stored
+	pub fn chunk_count(&self) -> usize {
+		self.chunks.lock().expect("chunk store lock poisoned").len()
+	}
+
+	/// Total bytes occupied by unique chunk payloads (i.e. after dedup)
+	pub fn unique_bytes(&self) -> u64 {
+		self.chunks
+			.lock()
+			.expect("chunk store lock poisoned")
+			.values()
+			.map(|entry| entry.data.len() as u64)
+			.sum()
+	}
+}
+
+/// A cached frame represented as an ordered list of deduplicated chunk references
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DedupFrame {
+	pub chunks: Vec<ChunkKey>,
+	pub total_len: usize,
+}
+
+/// Chunks frames with FastCDC and stores the resulting chunks in a shared, refcounted map
+#[derive(Default)]
+pub struct DedupStore {
+	chunker: Option<FastCdc>,
+	chunks: ChunkStore,
+}
+
+impl DedupStore {
+	pub fn new(chunker: FastCdc) -> Self {
+		Self {
+			chunker: Some(chunker),
+			chunks: ChunkStore::new(),
+		}
+	}
+
+	/// Chunk and store a frame's payload, returning its dedup'd representation and how many
+	/// bytes were saved by reusing already-stored chunks.
+	pub fn insert_frame(&self, data: &[u8]) -> (DedupFrame, u64) {
+		let chunker = self.chunker.as_ref().expect("DedupStore requires a chunker");
+
+		let mut frame = DedupFrame {
+			chunks: Vec::new(),
+			total_len: data.len(),
+		};
+		let mut offset = 0;
+		let mut stored_bytes = 0u64;
+
+		for cut in chunker.cut_points(data) {
+			let chunk = &data[offset..cut];
+			let key = *blake3::hash(chunk).as_bytes();
+			if !self.chunks.contains(&key) {
+				stored_bytes += chunk.len() as u64;
+			}
+			self.chunks.insert(chunk);
+			frame.chunks.push(key);
+			offset = cut;
+		}
+
+		let saved_bytes = data.len() as u64 - stored_bytes;
+		(frame, saved_bytes)
+	}
+
+	/// Release all chunks referenced by a frame, e.g. when its group is evicted
+	pub fn release_frame(&self, frame: &DedupFrame) {
+		for key in &frame.chunks {
+			self.chunks.release(key);
+		}
+	}
+
+	/// Number of distinct chunks currently stored across all frames
+	pub fn chunk_count(&self) -> usize {
+		self.chunks.chunk_count()
+	}
+
+	/// Total bytes actually occupied by unique chunk payloads
+	pub fn unique_bytes(&self) -> u64 {
+		self.chunks.unique_bytes()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_cut_points_cover_whole_input() {
+		let chunker = FastCdc::new(16, 64, 256);
+		let data = vec![7u8; 10_000];
+		let cuts = chunker.cut_points(&data);
+
+		assert_eq!(*cuts.last().unwrap(), data.len());
+		let mut prev = 0;
+		for cut in &cuts {
+			assert!(*cut > prev);
+			prev = *cut;
+		}
+	}
+
+	#[test]
+	fn test_cut_points_respect_max_size() {
+		let chunker = FastCdc::new(16, 64, 256);
+		let data = vec![0u8; 10_000]; // all-zero input never trips a fingerprint cut early
+		let cuts = chunker.cut_points(&data);
+
+		let mut prev = 0;
+		for cut in cuts {
+			assert!(cut - prev <= 256);
+			prev = cut;
+		}
+	}
+
+	#[test]
+	fn test_chunk_boundaries_stable_across_shifted_alignment() {
+		let chunker = FastCdc::new(16, 64, 256);
+		let mut data = vec![0u8; 5_000];
+		for (i, b) in data.iter_mut().enumerate() {
+			*b = (i * 37 % 251) as u8;
+		}
+
+		// Prepend extra bytes so the same content appears at a different alignment.
+		let mut shifted = vec![9u8; 123];
+		shifted.extend_from_slice(&data);
+
+		let store = DedupStore::new(chunker);
+		let (frame_a, _) = store.insert_frame(&data);
+		let (frame_b, saved) = store.insert_frame(&shifted);
+
+		// Some interior chunks should be shared even though the second input is shifted,
+		// since FastCDC cut points are content-defined rather than offset-defined.
+		let shared = frame_a.chunks.iter().filter(|c| frame_b.chunks.contains(c)).count();
+		assert!(shared > 0);
+		assert!(saved < shifted.len() as u64);
+	}
+
+	#[test]
+	fn test_refcount_reclaims_on_last_release() {
+		let store = ChunkStore::new();
+		let data = b"hello world";
+
+		let key = store.insert(data);
+		store.insert(data); // second reference to the same content
+		assert_eq!(store.chunk_count(), 1);
+
+		store.release(&key);
+		assert!(store.contains(&key)); // still referenced once
+		store.release(&key);
+		assert!(!store.contains(&key)); // fully reclaimed
+	}
+}
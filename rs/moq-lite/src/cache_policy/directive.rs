@@ -0,0 +1,276 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use crate::{CacheDecision, CachePolicy, Path};
+
+/// Parsed Cache-Control-style directives carried on a broadcast's announce metadata
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CacheDirectives {
+	/// `no-store`: never cache this broadcast
+	pub no_store: bool,
+	/// `no-cache`: cached entries must not be served without revalidation
+	pub no_cache: bool,
+	/// `must-revalidate`: once stale, an entry must not be served without revalidation
+	pub must_revalidate: bool,
+	/// `max-age=N`: freshness lifetime in seconds
+	pub max_age: Option<u64>,
+	/// `s-maxage=N`: freshness lifetime for shared (relay) caches, overrides `max-age`
+	pub s_maxage: Option<u64>,
+	/// `min-fresh=N`: minimum freshness the requester is willing to accept
+	pub min_fresh: Option<u64>,
+}
+
+impl CacheDirectives {
+	/// Parse a comma-separated directive list, e.g. `"no-store"` or `"max-age=30,must-revalidate"`.
+	///
+	/// Each token is either a bare flag or a `name=value` pair; the value may be quoted.
+	/// Unknown tokens are ignored so forward-compatible directives don't break parsing.
+	pub fn parse(s: &str) -> Self {
+		let mut directives = Self::default();
+
+		for token in s.split(',') {
+			let token = token.trim();
+			if token.is_empty() {
+				continue;
+			}
+
+			let (name, value) = match token.split_once('=') {
+				Some((name, value)) => (name.trim(), Some(value.trim().trim_matches('"'))),
+				None => (token, None),
+			};
+
+			match name {
+				"no-store" => directives.no_store = true,
+				"no-cache" => directives.no_cache = true,
+				"must-revalidate" => directives.must_revalidate = true,
+				"max-age" => directives.max_age = value.and_then(|v| u64::from_str(v).ok()),
+				"s-maxage" => directives.s_maxage = value.and_then(|v| u64::from_str(v).ok()),
+				"min-fresh" => directives.min_fresh = value.and_then(|v| u64::from_str(v).ok()),
+				_ => {} // unknown directives are ignored
+			}
+		}
+
+		directives
+	}
+
+	/// Whether this broadcast should never be stored at all. `no-cache` is stricter than plain
+	/// freshness expiry (entries must be revalidated even fresh), so since this relay has no
+	/// revalidation path, we treat it the same as `no-store`: never hold an entry it can't
+	/// safely keep serving.
+	pub fn forbids_storage(&self) -> bool {
+		self.no_store || self.no_cache
+	}
+
+	/// The effective TTL for backup retention: `s-maxage` takes precedence over `max-age`. If
+	/// `must-revalidate` is set but no explicit freshness lifetime was given, the backup is
+	/// treated as immediately stale rather than kept indefinitely, since this relay can't
+	/// actually revalidate it.
+	pub fn effective_backup_ttl(&self) -> Option<u64> {
+		self.s_maxage.or(self.max_age).or(self.must_revalidate.then_some(0))
+	}
+}
+
+/// Cache policy driven by Cache-Control-style directives carried on each broadcast's announce
+/// metadata, letting a publisher opt individual broadcasts in/out of relay caching.
+///
+/// Directives are looked up per broadcast path rather than applied globally: call
+/// [`Self::set_broadcast_directives`] when a broadcast is announced (or re-announced) with new
+/// metadata, and [`Self::clear_broadcast_directives`] when it's unannounced. A broadcast with no
+/// recorded directives falls back to `default_directives` (e.g. the relay-wide config default).
+#[derive(Debug, Default)]
+pub struct DirectiveCachePolicy {
+	default_directives: CacheDirectives,
+	overrides: Mutex<HashMap<Path, CacheDirectives>>,
+}
+
+impl DirectiveCachePolicy {
+	/// Create a policy with no directives by default (equivalent to `AlwaysCachePolicy` until
+	/// broadcasts announce their own directives)
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Create a policy whose fallback, for broadcasts with no per-broadcast override, is parsed
+	/// from `raw` (e.g. a relay-wide config default)
+	pub fn with_default_directives(raw: &str) -> Self {
+		Self {
+			default_directives: CacheDirectives::parse(raw),
+			overrides: Mutex::new(HashMap::new()),
+		}
+	}
+
+	/// Record the directives announced for `path`'s metadata, parsed from the raw
+	/// Cache-Control-style string at admission time
+	pub fn set_broadcast_directives(&self, path: &Path, raw: &str) {
+		let mut overrides = self.overrides.lock().expect("lock poisoned");
+		overrides.insert(path.clone(), CacheDirectives::parse(raw));
+	}
+
+	/// Stop tracking directives for a broadcast, e.g. once it's unannounced
+	pub fn clear_broadcast_directives(&self, path: &Path) {
+		self.overrides.lock().expect("lock poisoned").remove(path);
+	}
+
+	/// The directives in effect for `path`: its own announced directives if any, else the
+	/// relay-wide default
+	fn directives_for(&self, path: &Path) -> CacheDirectives {
+		let overrides = self.overrides.lock().expect("lock poisoned");
+		overrides.get(path).cloned().unwrap_or_else(|| self.default_directives.clone())
+	}
+
+	/// The effective backup TTL advertised by `path`'s directives, if any
+	pub fn effective_ttl_for(&self, path: &Path) -> Option<u64> {
+		self.directives_for(path).effective_backup_ttl()
+	}
+
+	/// The effective backup TTL from the relay-wide default directives, ignoring any
+	/// per-broadcast overrides. Used by callers that only have access to the path-less
+	/// [`CachePolicy::should_keep_backup`] signature.
+	pub fn default_effective_ttl(&self) -> Option<u64> {
+		self.default_directives.effective_backup_ttl()
+	}
+}
+
+impl CachePolicy for DirectiveCachePolicy {
+	fn should_cache_broadcast(&self, path: &Path) -> CacheDecision {
+		if self.directives_for(path).forbids_storage() {
+			CacheDecision::NoCache
+		} else {
+			CacheDecision::Cache
+		}
+	}
+
+	fn should_cache_track(&self, broadcast_path: &Path, _track_name: &str, _priority: u8) -> CacheDecision {
+		self.should_cache_broadcast(broadcast_path)
+	}
+
+	fn should_cache_group(&self, _sequence: u64, _estimated_size: Option<u64>) -> CacheDecision {
+		// No broadcast path is available at this call site, so only the relay-wide default
+		// directives apply here; per-broadcast overrides are enforced in `should_cache_broadcast`.
+		if self.default_directives.forbids_storage() {
+			CacheDecision::NoCache
+		} else {
+			CacheDecision::Cache
+		}
+	}
+
+	fn should_cache_frame(&self, _frame_size: u64) -> CacheDecision {
+		if self.default_directives.forbids_storage() {
+			CacheDecision::NoCache
+		} else {
+			CacheDecision::Cache
+		}
+	}
+
+	fn should_keep_backup(&self, age_seconds: u64, _backup_count: usize) -> bool {
+		// No broadcast path is available at this call site either; use `effective_ttl_for` with
+		// the broadcast's path directly wherever it's known (e.g. the backup sweeper).
+		match self.default_directives.effective_backup_ttl() {
+			Some(ttl) => age_seconds < ttl,
+			None => true,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_parse_no_store() {
+		let d = CacheDirectives::parse("no-store");
+		assert!(d.no_store);
+	}
+
+	#[test]
+	fn test_parse_max_age_and_quoted_value() {
+		let d = CacheDirectives::parse(r#"max-age="30", s-maxage=10"#);
+		assert_eq!(d.max_age, Some(30));
+		assert_eq!(d.s_maxage, Some(10));
+		assert_eq!(d.effective_backup_ttl(), Some(10));
+	}
+
+	#[test]
+	fn test_parse_unknown_tokens_ignored() {
+		let d = CacheDirectives::parse("no-store, stale-while-revalidate=60");
+		assert!(d.no_store);
+		assert_eq!(d.max_age, None);
+	}
+
+	#[test]
+	fn test_no_cache_forbids_storage_like_no_store() {
+		let d = CacheDirectives::parse("no-cache");
+		assert!(!d.no_store);
+		assert!(d.no_cache);
+		assert!(d.forbids_storage());
+	}
+
+	#[test]
+	fn test_must_revalidate_without_max_age_means_immediately_stale() {
+		let d = CacheDirectives::parse("must-revalidate");
+		assert_eq!(d.effective_backup_ttl(), Some(0));
+	}
+
+	#[test]
+	fn test_must_revalidate_does_not_override_explicit_max_age() {
+		let d = CacheDirectives::parse("must-revalidate,max-age=30");
+		assert_eq!(d.effective_backup_ttl(), Some(30));
+	}
+
+	#[test]
+	fn test_no_store_overrides_caching() {
+		let policy = DirectiveCachePolicy::with_default_directives("no-store");
+		assert_eq!(
+			policy.should_cache_broadcast(&Path::new("live/stream")),
+			CacheDecision::NoCache
+		);
+	}
+
+	#[test]
+	fn test_per_broadcast_override_is_independent_of_other_broadcasts() {
+		let policy = DirectiveCachePolicy::new();
+		let opted_out = Path::new("live/private");
+		let opted_in = Path::new("live/public");
+
+		policy.set_broadcast_directives(&opted_out, "no-store");
+
+		assert_eq!(policy.should_cache_broadcast(&opted_out), CacheDecision::NoCache);
+		assert_eq!(policy.should_cache_broadcast(&opted_in), CacheDecision::Cache);
+	}
+
+	#[test]
+	fn test_clear_broadcast_directives_reverts_to_default() {
+		let policy = DirectiveCachePolicy::new();
+		let path = Path::new("live/stream");
+
+		policy.set_broadcast_directives(&path, "no-store");
+		assert_eq!(policy.should_cache_broadcast(&path), CacheDecision::NoCache);
+
+		policy.clear_broadcast_directives(&path);
+		assert_eq!(policy.should_cache_broadcast(&path), CacheDecision::Cache);
+	}
+
+	#[test]
+	fn test_effective_ttl_for_uses_per_broadcast_override() {
+		let policy = DirectiveCachePolicy::with_default_directives("max-age=100");
+		let overridden = Path::new("live/stream");
+		policy.set_broadcast_directives(&overridden, "s-maxage=10");
+
+		assert_eq!(policy.effective_ttl_for(&overridden), Some(10));
+		assert_eq!(policy.effective_ttl_for(&Path::new("live/other")), Some(100));
+	}
+
+	#[test]
+	fn test_backup_ttl_from_s_maxage() {
+		let policy = DirectiveCachePolicy::with_default_directives("max-age=100,s-maxage=10");
+		assert!(policy.should_keep_backup(5, 1));
+		assert!(!policy.should_keep_backup(10, 1));
+	}
+
+	#[test]
+	fn test_no_directives_keeps_backup_forever() {
+		let policy = DirectiveCachePolicy::new();
+		assert!(policy.should_keep_backup(u64::MAX, 0));
+	}
+}
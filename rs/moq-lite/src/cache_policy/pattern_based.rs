@@ -1,6 +1,10 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
 use glob::Pattern;
 
-use crate::{CacheDecision, CachePolicy, Path};
+use crate::{CacheDecision, CacheEntryStat, CachePolicy, Clock, EvictId, Path, RealClock};
 
 /// Pattern-based cache policy with configurable rules
 #[derive(Debug, Clone)]
@@ -21,6 +25,18 @@ pub struct PatternBasedCachePolicy {
 	pub max_frames_per_group: usize,
 	/// Maximum frame size in bytes (0 = unlimited)
 	pub max_frame_size_bytes: u64,
+	/// Whether cached frames should be content-defined-chunked and deduplicated
+	pub dedup_enabled: bool,
+	/// Maximum total cache size in bytes across all broadcasts (0 = unlimited)
+	pub max_cache_size_bytes: u64,
+	/// Maximum cache size per broadcast in bytes (0 = unlimited)
+	pub max_broadcast_size_bytes: u64,
+	/// Maximum number of tracks to cache per broadcast (0 = unlimited)
+	pub max_tracks_per_broadcast: usize,
+	/// Clock used to compute backup ages internally; defaults to the real system clock
+	clock: Arc<dyn Clock>,
+	/// Insertion time of each currently-tracked backup broadcast, keyed by path
+	backup_inserted_at: Arc<Mutex<HashMap<Path, Instant>>>,
 }
 
 impl Default for PatternBasedCachePolicy {
@@ -34,6 +50,12 @@ impl Default for PatternBasedCachePolicy {
 			max_groups_per_track: 1, // Only latest group by default
 			max_frames_per_group: 0,
 			max_frame_size_bytes: 0,
+			dedup_enabled: false,
+			max_cache_size_bytes: 0,
+			max_broadcast_size_bytes: 0,
+			max_tracks_per_broadcast: 0,
+			clock: Arc::new(RealClock),
+			backup_inserted_at: Arc::new(Mutex::new(HashMap::new())),
 		}
 	}
 }
@@ -98,6 +120,137 @@ impl PatternBasedCachePolicy {
 		self
 	}
 
+	/// Enable content-defined chunk deduplication for cached frames
+	pub fn with_dedup_enabled(mut self, enabled: bool) -> Self {
+		self.dedup_enabled = enabled;
+		self
+	}
+
+	/// Set the total cache size budget in bytes
+	pub fn with_max_cache_size(mut self, bytes: u64) -> Self {
+		self.max_cache_size_bytes = bytes;
+		self
+	}
+
+	/// Set the per-broadcast cache size budget in bytes
+	pub fn with_max_broadcast_size(mut self, bytes: u64) -> Self {
+		self.max_broadcast_size_bytes = bytes;
+		self
+	}
+
+	/// Set max tracks per broadcast
+	pub fn with_max_tracks_per_broadcast(mut self, max: usize) -> Self {
+		self.max_tracks_per_broadcast = max;
+		self
+	}
+
+	/// Use a custom clock (e.g. [`crate::MockClock`]) instead of the real system clock
+	pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+		self.clock = clock;
+		self
+	}
+
+	/// Whether admitting a new group would exceed `max_groups_per_track`, meaning the oldest
+	/// tracked group should be evicted to make room (ring-buffer "keep latest N" behavior).
+	/// `current_groups` should be the count *before* the new group is inserted.
+	pub fn should_evict_oldest_group(&self, current_groups: usize) -> bool {
+		self.max_groups_per_track > 0 && current_groups >= self.max_groups_per_track
+	}
+
+	/// Record that a backup broadcast was just inserted, so its age can be computed internally
+	pub fn record_backup_insertion(&self, path: &Path) {
+		let mut inserted_at = self.backup_inserted_at.lock().expect("lock poisoned");
+		inserted_at.insert(path.clone(), self.clock.now());
+	}
+
+	/// Stop tracking a backup broadcast, e.g. once it's evicted or promoted back to primary
+	pub fn forget_backup(&self, path: &Path) {
+		let mut inserted_at = self.backup_inserted_at.lock().expect("lock poisoned");
+		inserted_at.remove(path);
+	}
+
+	/// Seconds elapsed since a tracked backup broadcast was inserted, if it's still tracked
+	pub fn backup_age_seconds(&self, path: &Path) -> Option<u64> {
+		let inserted_at = self.backup_inserted_at.lock().expect("lock poisoned");
+		inserted_at.get(path).map(|inserted| self.clock.now().duration_since(*inserted).as_secs())
+	}
+
+	/// Like [`CachePolicy::should_keep_backup`], but computes the backup's age internally from
+	/// its tracked insertion time instead of requiring the caller to pass it in.
+	pub fn should_keep_tracked_backup(&self, path: &Path, backup_count: usize) -> bool {
+		match self.backup_age_seconds(path) {
+			Some(age_seconds) => self.should_keep_backup(age_seconds, backup_count),
+			None => true, // not tracked, nothing to expire
+		}
+	}
+
+	/// Walk tracked backups and stop tracking any whose age has reached `backup_max_age_seconds`, returning
+	/// the broadcasts that were swept so the caller can evict their cached data. Intended to be
+	/// called periodically by a background sweeper rather than waiting for the next request.
+	pub fn sweep_expired_backups(&self) -> Vec<Path> {
+		self.sweep_expired_backups_with_ttl(|_path| None)
+	}
+
+	/// Like [`Self::sweep_expired_backups`], but `ttl_for` can override the TTL used for a given
+	/// broadcast path (e.g. a directive-supplied `max-age`/`s-maxage`); `backup_max_age_seconds`
+	/// is only used as a fallback where `ttl_for` returns `None`. A path is never swept if neither
+	/// source has a TTL for it.
+	pub fn sweep_expired_backups_with_ttl(&self, ttl_for: impl Fn(&Path) -> Option<u64>) -> Vec<Path> {
+		let now = self.clock.now();
+		let mut inserted_at = self.backup_inserted_at.lock().expect("lock poisoned");
+		let expired: Vec<Path> = inserted_at
+			.iter()
+			.filter_map(|(path, inserted)| {
+				let ttl = ttl_for(path).or(if self.backup_max_age_seconds > 0 {
+					Some(self.backup_max_age_seconds)
+				} else {
+					None
+				})?;
+				(now.duration_since(*inserted).as_secs() >= ttl).then(|| path.clone())
+			})
+			.collect();
+
+		for path in &expired {
+			inserted_at.remove(path);
+		}
+
+		expired
+	}
+
+	/// Evict the lowest-priority, least-recently-used entries from `candidates` until
+	/// `current_bytes` would fall back under `limit` (0 = unlimited, never evicts).
+	fn evict_lru(current_bytes: u64, limit: u64, candidates: &[CacheEntryStat]) -> Vec<EvictId> {
+		if limit == 0 || current_bytes <= limit {
+			return Vec::new();
+		}
+
+		let mut ordered: Vec<&CacheEntryStat> = candidates.iter().collect();
+		// Lowest priority first, then least-recently-used first.
+		ordered.sort_by(|a, b| a.track_priority.cmp(&b.track_priority).then(a.last_access.cmp(&b.last_access)));
+
+		let mut freed = 0u64;
+		let mut evicted = Vec::new();
+		for entry in ordered {
+			if current_bytes.saturating_sub(freed) <= limit {
+				break;
+			}
+			freed += entry.byte_size;
+			evicted.push(EvictId {
+				broadcast_path: entry.broadcast_path.clone(),
+				track_name: entry.track_name.clone(),
+				group_sequence: entry.group_sequence,
+			});
+		}
+		evicted
+	}
+
+	/// Evict groups belonging to a single broadcast whose cumulative size exceeds
+	/// `max_broadcast_size_bytes`, using the same lowest-priority/LRU ordering as the
+	/// cache-wide [`CachePolicy::on_over_budget`] eviction.
+	pub fn on_broadcast_over_budget(&self, broadcast_bytes: u64, candidates: &[CacheEntryStat]) -> Vec<EvictId> {
+		Self::evict_lru(broadcast_bytes, self.max_broadcast_size_bytes, candidates)
+	}
+
 	/// Check if a path matches any of the patterns
 	fn matches_patterns(path: &str, patterns: &[Pattern]) -> bool {
 		patterns.iter().any(|p| p.matches(path))
@@ -136,13 +289,9 @@ impl CachePolicy for PatternBasedCachePolicy {
 	}
 
 	fn should_cache_group(&self, _sequence: u64, estimated_size: Option<u64>) -> CacheDecision {
-		// Note: Group count limits are enforced at insertion time, not here
-		// This just checks size limits
-
-		if self.max_frames_per_group > 0 {
-			// Would need frame count tracking to enforce this properly
-			// For now, we just accept groups
-		}
+		// Group-count limits (max_groups_per_track) and frame-count limits (max_frames_per_group)
+		// are enforced via should_admit_group/should_evict_oldest_group and should_admit_frame,
+		// which see the running counts this method doesn't have access to. This just checks size.
 
 		if let Some(size) = estimated_size {
 			if self.max_frame_size_bytes > 0 && size > self.max_frame_size_bytes {
@@ -174,11 +323,44 @@ impl CachePolicy for PatternBasedCachePolicy {
 
 		true
 	}
+
+	fn dedup_enabled(&self) -> bool {
+		self.dedup_enabled
+	}
+
+	fn on_over_budget(&self, current_bytes: u64, limit: u64, candidates: &[CacheEntryStat]) -> Vec<EvictId> {
+		Self::evict_lru(current_bytes, limit, candidates)
+	}
+
+	fn should_admit_frame(&self, _track: &Path, _group_seq: u64, current_frames_in_group: usize) -> CacheDecision {
+		if self.max_frames_per_group > 0 && current_frames_in_group >= self.max_frames_per_group {
+			CacheDecision::NoCache
+		} else {
+			CacheDecision::Cache
+		}
+	}
+
+	fn should_admit_group(&self, _track: &Path, _current_groups: usize) -> CacheDecision {
+		// Ring-buffer semantics: a new group is always admitted; see `should_evict_oldest_group`
+		// for whether admitting it means evicting the oldest tracked group.
+		CacheDecision::Cache
+	}
+
+	fn should_admit_track(&self, _broadcast: &Path, current_tracks: usize) -> CacheDecision {
+		if self.max_tracks_per_broadcast > 0 && current_tracks >= self.max_tracks_per_broadcast {
+			CacheDecision::NoCache
+		} else {
+			CacheDecision::Cache
+		}
+	}
 }
 
 #[cfg(test)]
 mod tests {
+	use std::time::{Duration, Instant};
+
 	use super::*;
+	use crate::MockClock;
 
 	#[test]
 	fn test_default_policy() {
@@ -255,4 +437,189 @@ mod tests {
 		assert!(!policy.should_keep_backup(400, 3)); // Age exceeded
 		assert!(!policy.should_keep_backup(100, 6)); // Count exceeded
 	}
+
+	#[test]
+	fn test_dedup_disabled_by_default() {
+		let policy = PatternBasedCachePolicy::new();
+		assert!(!policy.dedup_enabled());
+
+		let policy = policy.with_dedup_enabled(true);
+		assert!(policy.dedup_enabled());
+	}
+
+	fn stat(track_name: &str, group_sequence: u64, byte_size: u64, priority: u8, age: Duration) -> CacheEntryStat {
+		CacheEntryStat {
+			broadcast_path: Path::new("live/stream"),
+			track_name: track_name.to_string(),
+			group_sequence,
+			byte_size,
+			last_access: Instant::now() - age,
+			track_priority: priority,
+		}
+	}
+
+	#[test]
+	fn test_on_over_budget_unlimited_evicts_nothing() {
+		let policy = PatternBasedCachePolicy::new().with_max_cache_size(0);
+		let candidates = vec![stat("video", 1, 1024, 0, Duration::from_secs(0))];
+		assert!(policy.on_over_budget(10_000, 0, &candidates).is_empty());
+	}
+
+	#[test]
+	fn test_on_over_budget_evicts_lowest_priority_lru_first() {
+		let policy = PatternBasedCachePolicy::new().with_max_cache_size(1500);
+		let candidates = vec![
+			stat("video", 1, 1000, 255, Duration::from_secs(60)), // high priority, old: keep
+			stat("audio", 2, 1000, 0, Duration::from_secs(5)),    // low priority, recent: evict second
+			stat("audio", 3, 1000, 0, Duration::from_secs(50)),   // low priority, older: evict first
+		];
+
+		let evicted = policy.on_over_budget(3000, 1500, &candidates);
+
+		assert_eq!(evicted.len(), 2);
+		assert_eq!(evicted[0].group_sequence, 3); // older of the two low-priority entries first
+		assert_eq!(evicted[1].group_sequence, 2);
+	}
+
+	#[test]
+	fn test_on_broadcast_over_budget_honors_max_broadcast_size() {
+		let policy = PatternBasedCachePolicy::new().with_max_broadcast_size(1000);
+		let candidates = vec![
+			stat("video", 1, 600, 128, Duration::from_secs(30)),
+			stat("video", 2, 600, 128, Duration::from_secs(5)),
+		];
+
+		let evicted = policy.on_broadcast_over_budget(1200, &candidates);
+
+		assert_eq!(evicted.len(), 1);
+		assert_eq!(evicted[0].group_sequence, 1); // least-recently-used group evicted
+	}
+
+	#[test]
+	fn test_tracked_backup_age_uses_mock_clock() {
+		let clock = Arc::new(MockClock::new());
+		let policy = PatternBasedCachePolicy::new()
+			.with_clock(clock.clone())
+			.with_backup_max_age(30);
+
+		let path = Path::new("backup/stream");
+		policy.record_backup_insertion(&path);
+		assert!(policy.should_keep_tracked_backup(&path, 0));
+
+		clock.advance(Duration::from_secs(31));
+		assert!(!policy.should_keep_tracked_backup(&path, 0));
+	}
+
+	#[test]
+	fn test_sweep_expired_backups_drops_only_stale_entries() {
+		let clock = Arc::new(MockClock::new());
+		let policy = PatternBasedCachePolicy::new()
+			.with_clock(clock.clone())
+			.with_backup_max_age(10);
+
+		let fresh = Path::new("backup/fresh");
+		let stale = Path::new("backup/stale");
+		policy.record_backup_insertion(&stale);
+
+		clock.advance(Duration::from_secs(11));
+		policy.record_backup_insertion(&fresh);
+
+		let swept = policy.sweep_expired_backups();
+		assert_eq!(swept, vec![stale.clone()]);
+		assert_eq!(policy.backup_age_seconds(&fresh), Some(0));
+		assert_eq!(policy.backup_age_seconds(&stale), None);
+	}
+
+	#[test]
+	fn test_sweep_expired_backups_with_ttl_override_takes_precedence() {
+		let clock = Arc::new(MockClock::new());
+		// Static TTL would keep this for 3600s, but the override TTL expires it after 10s.
+		let policy = PatternBasedCachePolicy::new()
+			.with_clock(clock.clone())
+			.with_backup_max_age(3600);
+
+		let path = Path::new("backup/stream");
+		policy.record_backup_insertion(&path);
+
+		clock.advance(Duration::from_secs(11));
+		let swept = policy.sweep_expired_backups_with_ttl(|_| Some(10));
+		assert_eq!(swept, vec![path]);
+	}
+
+	#[test]
+	fn test_sweep_expired_backups_with_ttl_falls_back_to_static_max_age() {
+		let clock = Arc::new(MockClock::new());
+		let policy = PatternBasedCachePolicy::new()
+			.with_clock(clock.clone())
+			.with_backup_max_age(10);
+
+		let path = Path::new("backup/stream");
+		policy.record_backup_insertion(&path);
+
+		clock.advance(Duration::from_secs(11));
+		let swept = policy.sweep_expired_backups_with_ttl(|_| None);
+		assert_eq!(swept, vec![path]);
+	}
+
+	#[test]
+	fn test_sweep_expired_backups_with_ttl_never_expires_without_any_ttl() {
+		let clock = Arc::new(MockClock::new());
+		let policy = PatternBasedCachePolicy::new().with_clock(clock.clone());
+
+		let path = Path::new("backup/stream");
+		policy.record_backup_insertion(&path);
+
+		clock.advance(Duration::from_secs(1_000_000));
+		let swept = policy.sweep_expired_backups_with_ttl(|_| None);
+		assert!(swept.is_empty());
+	}
+
+	#[test]
+	fn test_should_admit_frame_boundary() {
+		let policy = PatternBasedCachePolicy::new().with_max_frames_per_group(3);
+		let track = Path::new("live/stream");
+
+		assert_eq!(policy.should_admit_frame(&track, 1, 2), CacheDecision::Cache);
+		assert_eq!(policy.should_admit_frame(&track, 1, 3), CacheDecision::NoCache); // exactly at limit
+		assert_eq!(policy.should_admit_frame(&track, 1, 4), CacheDecision::NoCache);
+	}
+
+	#[test]
+	fn test_should_admit_frame_unlimited_when_zero() {
+		let policy = PatternBasedCachePolicy::new().with_max_frames_per_group(0);
+		let track = Path::new("live/stream");
+
+		assert_eq!(policy.should_admit_frame(&track, 1, usize::MAX), CacheDecision::Cache);
+	}
+
+	#[test]
+	fn test_should_evict_oldest_group_boundary() {
+		let policy = PatternBasedCachePolicy::new().with_max_groups_per_track(2);
+
+		assert!(!policy.should_evict_oldest_group(1));
+		assert!(policy.should_evict_oldest_group(2)); // exactly at limit
+		assert!(policy.should_evict_oldest_group(3));
+	}
+
+	#[test]
+	fn test_should_evict_oldest_group_unlimited_when_zero() {
+		let policy = PatternBasedCachePolicy::new().with_max_groups_per_track(0);
+		assert!(!policy.should_evict_oldest_group(usize::MAX));
+	}
+
+	#[test]
+	fn test_should_admit_track_boundary() {
+		let policy = PatternBasedCachePolicy::new().with_max_tracks_per_broadcast(5);
+		let broadcast = Path::new("live/stream");
+
+		assert_eq!(policy.should_admit_track(&broadcast, 4), CacheDecision::Cache);
+		assert_eq!(policy.should_admit_track(&broadcast, 5), CacheDecision::NoCache); // exactly at limit
+	}
+
+	#[test]
+	fn test_should_admit_track_unlimited_when_zero() {
+		let policy = PatternBasedCachePolicy::new().with_max_tracks_per_broadcast(0);
+		let broadcast = Path::new("live/stream");
+		assert_eq!(policy.should_admit_track(&broadcast, usize::MAX), CacheDecision::Cache);
+	}
 }
@@ -1,5 +1,8 @@
 use clap::Parser;
-use moq_lite::{AlwaysCachePolicy, CachePolicy, NeverCachePolicy, PatternBasedCachePolicy};
+use moq_lite::{
+	AlwaysCachePolicy, CacheDecision, CacheEntryStat, CachePolicy, DirectiveCachePolicy, EvictId, NeverCachePolicy, Path,
+	PatternBasedCachePolicy,
+};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
@@ -26,6 +29,11 @@ pub struct CachePolicyConfig {
 	/// Global cache limits
 	#[command(flatten)]
 	pub limits: CacheLimits,
+
+	/// How often (in seconds) the background sweeper checks for expired backup broadcasts.
+	/// 0 disables the sweeper; expiry is then only checked when a new request touches a backup.
+	#[arg(long, default_value_t = 60)]
+	pub cleaning_interval_seconds: u64,
 }
 
 impl Default for CachePolicyConfig {
@@ -36,6 +44,7 @@ impl Default for CachePolicyConfig {
 			track: TrackCachePolicy::default(),
 			group: GroupCachePolicy::default(),
 			limits: CacheLimits::default(),
+			cleaning_interval_seconds: 60,
 		}
 	}
 }
@@ -61,6 +70,13 @@ pub struct BroadcastCachePolicy {
 	/// Maximum number of backup broadcasts to keep per path
 	#[arg(long, default_value_t = 0)]
 	pub backup_max_count: usize,
+
+	/// Default Cache-Control-style directives (e.g. "no-store", "max-age=30") for broadcasts that
+	/// have no directives of their own. Setting this layers a directive policy on top of the
+	/// pattern-based one: directives can veto caching a broadcast the patterns allow, and their
+	/// TTL replaces `backup_max_age_seconds`, but they cannot cache a broadcast the patterns reject.
+	#[arg(long)]
+	pub directives: Option<String>,
 }
 
 impl Default for BroadcastCachePolicy {
@@ -70,6 +86,7 @@ impl Default for BroadcastCachePolicy {
 			exclude_patterns: Vec::new(),
 			backup_max_age_seconds: 0, // No TTL by default
 			backup_max_count: 0,       // Unlimited by default
+			directives: None,          // No directives by default
 		}
 	}
 }
@@ -133,6 +150,11 @@ pub struct CacheLimits {
 	/// Maximum frame size in bytes (0 = unlimited)
 	#[arg(long, default_value_t = 0)]
 	pub max_frame_size_bytes: u64,
+
+	/// Deduplicate cached frames by content-defined chunk, sharing storage across groups and
+	/// broadcasts (e.g. repeated init segments or backup broadcasts)
+	#[arg(long, default_value_t = false)]
+	pub dedup_enabled: bool,
 }
 
 impl Default for CacheLimits {
@@ -141,15 +163,33 @@ impl Default for CacheLimits {
 			max_cache_size_bytes: 0,
 			max_broadcast_size_bytes: 0,
 			max_frame_size_bytes: 0,
+			dedup_enabled: false,
 		}
 	}
 }
 
+/// The result of [`CachePolicyConfig::build`]: the `CachePolicy` trait object installed into the
+/// cache, plus the concrete policy types backing it (when applicable), so callers that need more
+/// than the trait surface — e.g. the backup sweeper, or per-broadcast budget eviction — can reach
+/// the exact same shared state rather than constructing a disjoint second instance.
+pub struct BuiltCachePolicy {
+	/// The policy installed into the cache
+	pub policy: Arc<dyn CachePolicy>,
+	/// The pattern-based policy backing `policy`, if one was built
+	pub pattern: Option<Arc<PatternBasedCachePolicy>>,
+	/// The directive policy backing `policy`, if one was built
+	pub directive: Option<Arc<DirectiveCachePolicy>>,
+}
+
 impl CachePolicyConfig {
 	/// Create a cache policy implementation from this configuration
-	pub fn build(&self) -> anyhow::Result<Arc<dyn CachePolicy>> {
+	pub fn build(&self) -> anyhow::Result<BuiltCachePolicy> {
 		if !self.cache_enabled {
-			return Ok(Arc::new(NeverCachePolicy));
+			return Ok(BuiltCachePolicy {
+				policy: Arc::new(NeverCachePolicy),
+				pattern: None,
+				directive: None,
+			});
 		}
 
 		// If using default patterns and no limits, use AlwaysCachePolicy for backward compatibility
@@ -159,22 +199,161 @@ impl CachePolicyConfig {
 			&& self.broadcast.backup_max_age_seconds == 0
 			&& self.broadcast.backup_max_count == 0
 			&& self.limits.max_frame_size_bytes == 0
+			&& self.limits.max_cache_size_bytes == 0
+			&& self.limits.max_broadcast_size_bytes == 0
+			&& self.track.max_tracks_per_broadcast == 0
+			&& self.group.max_groups_per_track == 1
+			&& self.group.max_frames_per_group == 0
+			&& !self.limits.dedup_enabled
+			&& self.broadcast.directives.is_none()
 		{
-			return Ok(Arc::new(AlwaysCachePolicy));
+			return Ok(BuiltCachePolicy {
+				policy: Arc::new(AlwaysCachePolicy),
+				pattern: None,
+				directive: None,
+			});
 		}
 
 		// Build pattern-based policy
-		let policy = PatternBasedCachePolicy::new()
-			.with_cache_patterns(self.broadcast.cache_patterns.clone())?
-			.with_exclude_patterns(self.broadcast.exclude_patterns.clone())?
-			.with_min_track_priority(self.track.min_priority)
-			.with_backup_max_age(self.broadcast.backup_max_age_seconds)
-			.with_backup_max_count(self.broadcast.backup_max_count)
-			.with_max_groups_per_track(self.group.max_groups_per_track)
-			.with_max_frames_per_group(self.group.max_frames_per_group)
-			.with_max_frame_size(self.limits.max_frame_size_bytes);
-
-		Ok(Arc::new(policy))
+		let pattern = Arc::new(
+			PatternBasedCachePolicy::new()
+				.with_cache_patterns(self.broadcast.cache_patterns.clone())?
+				.with_exclude_patterns(self.broadcast.exclude_patterns.clone())?
+				.with_min_track_priority(self.track.min_priority)
+				.with_backup_max_age(self.broadcast.backup_max_age_seconds)
+				.with_backup_max_count(self.broadcast.backup_max_count)
+				.with_max_groups_per_track(self.group.max_groups_per_track)
+				.with_max_frames_per_group(self.group.max_frames_per_group)
+				.with_max_frame_size(self.limits.max_frame_size_bytes)
+				.with_dedup_enabled(self.limits.dedup_enabled)
+				.with_max_cache_size(self.limits.max_cache_size_bytes)
+				.with_max_broadcast_size(self.limits.max_broadcast_size_bytes)
+				.with_max_tracks_per_broadcast(self.track.max_tracks_per_broadcast),
+		);
+
+		// Layer per-broadcast Cache-Control directives on top of the pattern-based policy
+		if let Some(raw) = &self.broadcast.directives {
+			let directive = Arc::new(DirectiveCachePolicy::with_default_directives(raw));
+			return Ok(BuiltCachePolicy {
+				policy: Arc::new(CombinedCachePolicy {
+					pattern: pattern.clone(),
+					directive: directive.clone(),
+				}),
+				pattern: Some(pattern),
+				directive: Some(directive),
+			});
+		}
+
+		Ok(BuiltCachePolicy {
+			policy: pattern.clone(),
+			pattern: Some(pattern),
+			directive: None,
+		})
+	}
+
+	/// Spawn a background task that periodically sweeps expired backup broadcasts from `built`'s
+	/// pattern-based policy, so stale backups are dropped even if no new request touches them.
+	/// When `built` also has a directive policy, a broadcast's Cache-Control `max-age`/`s-maxage`
+	/// overrides `backup_max_age_seconds` here too, matching the pull-based
+	/// `CombinedCachePolicy::should_keep_backup` semantics. Returns `None` if the sweeper is
+	/// disabled via `cleaning_interval_seconds = 0`, or if `built` has no pattern-based policy to
+	/// sweep (e.g. `AlwaysCachePolicy`/`NeverCachePolicy`).
+	pub fn spawn_backup_sweeper(&self, built: &BuiltCachePolicy) -> Option<tokio::task::JoinHandle<()>> {
+		if self.cleaning_interval_seconds == 0 {
+			return None;
+		}
+
+		let policy = built.pattern.clone()?;
+		let directive = built.directive.clone();
+		let interval = std::time::Duration::from_secs(self.cleaning_interval_seconds);
+		Some(tokio::spawn(async move {
+			let mut ticker = tokio::time::interval(interval);
+			loop {
+				ticker.tick().await;
+				match &directive {
+					Some(directive) => {
+						policy.sweep_expired_backups_with_ttl(|path| directive.effective_ttl_for(path));
+					}
+					None => {
+						policy.sweep_expired_backups();
+					}
+				}
+			}
+		}))
+	}
+}
+
+/// Combines glob-pattern broadcast matching with Cache-Control-style directives carried on a
+/// broadcast's announce metadata, so either side can veto caching a broadcast
+struct CombinedCachePolicy {
+	pattern: Arc<PatternBasedCachePolicy>,
+	directive: Arc<DirectiveCachePolicy>,
+}
+
+impl CachePolicy for CombinedCachePolicy {
+	fn should_cache_broadcast(&self, path: &Path) -> CacheDecision {
+		if !self.pattern.should_cache_broadcast(path).should_cache() {
+			return CacheDecision::NoCache;
+		}
+		self.directive.should_cache_broadcast(path)
+	}
+
+	fn should_cache_track(&self, broadcast_path: &Path, track_name: &str, priority: u8) -> CacheDecision {
+		if !self
+			.pattern
+			.should_cache_track(broadcast_path, track_name, priority)
+			.should_cache()
+		{
+			return CacheDecision::NoCache;
+		}
+		self.directive.should_cache_track(broadcast_path, track_name, priority)
+	}
+
+	fn should_cache_group(&self, sequence: u64, estimated_size: Option<u64>) -> CacheDecision {
+		if !self.pattern.should_cache_group(sequence, estimated_size).should_cache() {
+			return CacheDecision::NoCache;
+		}
+		self.directive.should_cache_group(sequence, estimated_size)
+	}
+
+	fn should_cache_frame(&self, frame_size: u64) -> CacheDecision {
+		if !self.pattern.should_cache_frame(frame_size).should_cache() {
+			return CacheDecision::NoCache;
+		}
+		self.directive.should_cache_frame(frame_size)
+	}
+
+	fn should_keep_backup(&self, age_seconds: u64, backup_count: usize) -> bool {
+		// A directive TTL (s-maxage/max-age) overrides the statically configured
+		// backup_max_age_seconds, but the configured backup count limit still applies.
+		if self.directive.default_effective_ttl().is_some() {
+			if self.pattern.backup_max_count > 0 && backup_count > self.pattern.backup_max_count {
+				return false;
+			}
+			return self.directive.should_keep_backup(age_seconds, backup_count);
+		}
+
+		self.pattern.should_keep_backup(age_seconds, backup_count)
+	}
+
+	fn dedup_enabled(&self) -> bool {
+		self.pattern.dedup_enabled()
+	}
+
+	fn on_over_budget(&self, current_bytes: u64, limit: u64, candidates: &[CacheEntryStat]) -> Vec<EvictId> {
+		self.pattern.on_over_budget(current_bytes, limit, candidates)
+	}
+
+	fn should_admit_frame(&self, track: &Path, group_seq: u64, current_frames_in_group: usize) -> CacheDecision {
+		self.pattern.should_admit_frame(track, group_seq, current_frames_in_group)
+	}
+
+	fn should_admit_group(&self, track: &Path, current_groups: usize) -> CacheDecision {
+		self.pattern.should_admit_group(track, current_groups)
+	}
+
+	fn should_admit_track(&self, broadcast: &Path, current_tracks: usize) -> CacheDecision {
+		self.pattern.should_admit_track(broadcast, current_tracks)
 	}
 }
 
@@ -188,6 +367,7 @@ mod tests {
 		assert!(config.cache_enabled);
 		assert_eq!(config.broadcast.cache_patterns, vec!["**"]);
 		assert_eq!(config.group.max_groups_per_track, 1);
+		assert_eq!(config.cleaning_interval_seconds, 60);
 	}
 
 	#[test]
@@ -199,6 +379,7 @@ mod tests {
 				exclude_patterns: vec!["*/archive/*".to_string()],
 				backup_max_age_seconds: 300,
 				backup_max_count: 3,
+				directives: None,
 			},
 			track: TrackCachePolicy {
 				max_tracks_per_broadcast: 10,
@@ -212,6 +393,7 @@ mod tests {
 				max_cache_size_bytes: 100 * 1024 * 1024, // 100MB
 				max_broadcast_size_bytes: 10 * 1024 * 1024, // 10MB
 				max_frame_size_bytes: 1024 * 1024,        // 1MB
+				dedup_enabled: false,
 			},
 		};
 
@@ -222,23 +404,183 @@ mod tests {
 	#[test]
 	fn test_build_always_cache_policy() {
 		let config = CachePolicyConfig::default();
-		let _policy = config.build().unwrap();
-		// Should use AlwaysCachePolicy for default config
+		let built = config.build().unwrap();
+		// Should use AlwaysCachePolicy for default config, with no concrete policy to reach through
+		assert!(built.pattern.is_none());
+		assert!(built.directive.is_none());
 	}
 
 	#[test]
 	fn test_build_never_cache_policy() {
 		let mut config = CachePolicyConfig::default();
 		config.cache_enabled = false;
-		let _policy = config.build().unwrap();
+		let built = config.build().unwrap();
 		// Should use NeverCachePolicy when disabled
+		assert!(built.pattern.is_none());
 	}
 
 	#[test]
 	fn test_build_pattern_based_policy() {
 		let mut config = CachePolicyConfig::default();
 		config.broadcast.cache_patterns = vec!["live/**".to_string()];
-		let _policy = config.build().unwrap();
-		// Should use PatternBasedCachePolicy with custom patterns
+		let built = config.build().unwrap();
+		// Should use PatternBasedCachePolicy with custom patterns, reachable via `built.pattern`
+		assert!(built.pattern.is_some());
+	}
+
+	#[test]
+	fn test_build_combined_policy_with_directives() {
+		let mut config = CachePolicyConfig::default();
+		config.broadcast.directives = Some("no-store".to_string());
+		let built = config.build().unwrap();
+
+		assert_eq!(
+			built.policy.should_cache_broadcast(&Path::new("live/stream")),
+			CacheDecision::NoCache
+		);
+	}
+
+	#[test]
+	fn test_build_combined_policy_directives_are_per_broadcast() {
+		let mut config = CachePolicyConfig::default();
+		config.broadcast.directives = Some("max-age=100".to_string());
+		let built = config.build().unwrap();
+		let directive = built.directive.expect("directive policy reachable");
+
+		let opted_out = Path::new("live/private");
+		directive.set_broadcast_directives(&opted_out, "no-store");
+
+		assert_eq!(built.policy.should_cache_broadcast(&opted_out), CacheDecision::NoCache);
+		assert_eq!(
+			built.policy.should_cache_broadcast(&Path::new("live/public")),
+			CacheDecision::Cache
+		);
+	}
+
+	#[test]
+	fn test_combined_policy_directive_ttl_overrides_static_max_age() {
+		let mut config = CachePolicyConfig::default();
+		config.broadcast.backup_max_age_seconds = 3600;
+		config.broadcast.directives = Some("max-age=10".to_string());
+		let built = config.build().unwrap();
+
+		// Static backup_max_age_seconds would keep this, but the directive TTL takes over.
+		assert!(!built.policy.should_keep_backup(20, 0));
+		assert!(built.policy.should_keep_backup(5, 0));
+	}
+
+	#[test]
+	fn test_build_dedup_enabled() {
+		let mut config = CachePolicyConfig::default();
+		config.limits.dedup_enabled = true;
+		let built = config.build().unwrap();
+
+		assert!(built.policy.dedup_enabled());
+	}
+
+	#[test]
+	fn test_build_enforces_max_cache_size_via_eviction() {
+		let mut config = CachePolicyConfig::default();
+		config.limits.max_cache_size_bytes = 1000;
+		let built = config.build().unwrap();
+
+		let candidates = vec![CacheEntryStat {
+			broadcast_path: Path::new("live/stream"),
+			track_name: "video".to_string(),
+			group_sequence: 1,
+			byte_size: 500,
+			last_access: std::time::Instant::now(),
+			track_priority: 0,
+		}];
+
+		assert_eq!(built.policy.on_over_budget(1500, 1000, &candidates).len(), 1);
+	}
+
+	#[test]
+	fn test_build_enforces_max_broadcast_size_via_reachable_pattern_policy() {
+		let mut config = CachePolicyConfig::default();
+		config.limits.max_broadcast_size_bytes = 1000;
+		let built = config.build().unwrap();
+		let pattern = built.pattern.expect("pattern policy reachable");
+
+		let candidates = vec![CacheEntryStat {
+			broadcast_path: Path::new("live/stream"),
+			track_name: "video".to_string(),
+			group_sequence: 1,
+			byte_size: 500,
+			last_access: std::time::Instant::now(),
+			track_priority: 0,
+		}];
+
+		assert_eq!(pattern.on_broadcast_over_budget(1500, &candidates).len(), 1);
+	}
+
+	#[tokio::test]
+	async fn test_spawn_backup_sweeper_disabled_returns_none() {
+		let mut config = CachePolicyConfig::default();
+		config.cleaning_interval_seconds = 0;
+		config.broadcast.backup_max_age_seconds = 30;
+
+		let built = config.build().unwrap();
+		assert!(config.spawn_backup_sweeper(&built).is_none());
+	}
+
+	#[tokio::test]
+	async fn test_spawn_backup_sweeper_enabled_for_the_policy_build_installed() {
+		let mut config = CachePolicyConfig::default();
+		config.broadcast.backup_max_age_seconds = 30;
+		config.cleaning_interval_seconds = 1;
+
+		let built = config.build().unwrap();
+		let pattern = built.pattern.clone().expect("pattern policy reachable");
+
+		let path = Path::new("backup/stream");
+		pattern.record_backup_insertion(&path);
+		assert!(pattern.backup_age_seconds(&path).is_some());
+
+		let handle = config.spawn_backup_sweeper(&built).expect("sweeper enabled");
+		handle.abort();
+	}
+
+	#[test]
+	fn test_backup_sweeper_ttl_honors_directive_override_without_static_max_age() {
+		let mut config = CachePolicyConfig::default();
+		// No backup_max_age_seconds configured: the pull-based CombinedCachePolicy::should_keep_backup
+		// path still expires backups via the directive TTL, and the sweeper must match it.
+		config.broadcast.directives = Some("max-age=0".to_string());
+		config.cleaning_interval_seconds = 1;
+
+		let built = config.build().unwrap();
+		let pattern = built.pattern.clone().expect("pattern policy reachable");
+		let directive = built.directive.clone().expect("directive policy reachable");
+
+		let path = Path::new("backup/stream");
+		pattern.record_backup_insertion(&path);
+
+		// Same closure spawn_backup_sweeper installs when a directive policy is present.
+		let swept = pattern.sweep_expired_backups_with_ttl(|p| directive.effective_ttl_for(p));
+		assert_eq!(swept, vec![path]);
+	}
+
+	#[test]
+	fn test_build_enforces_max_tracks_per_broadcast() {
+		let mut config = CachePolicyConfig::default();
+		config.track.max_tracks_per_broadcast = 2;
+		let built = config.build().unwrap();
+
+		let broadcast = Path::new("live/stream");
+		assert_eq!(built.policy.should_admit_track(&broadcast, 1), CacheDecision::Cache);
+		assert_eq!(built.policy.should_admit_track(&broadcast, 2), CacheDecision::NoCache);
+	}
+
+	#[test]
+	fn test_should_evict_oldest_group_reachable_via_built_pattern() {
+		let mut config = CachePolicyConfig::default();
+		config.group.max_groups_per_track = 2;
+		let built = config.build().unwrap();
+		let pattern = built.pattern.expect("pattern policy reachable");
+
+		assert!(!pattern.should_evict_oldest_group(1));
+		assert!(pattern.should_evict_oldest_group(2));
 	}
 }